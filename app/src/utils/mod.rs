@@ -1,8 +1,10 @@
+pub mod multi_pool;
+
 use bindings::i_uniswap_v3_pool::IUniswapV3Pool;
 use bindings::uniswap_v3_factory::UniswapV3Factory;
 use ethers::abi::Address;
 use ethers::prelude::*;
-use ethers::providers::Provider;
+use ethers::providers::{Provider, Ws};
 use num_bigfloat::BigFloat;
 use std::sync::Arc;
 
@@ -28,25 +30,44 @@ pub fn convert(q64_96: U256) -> BigFloat {
         + BigFloat::from(least_sig))
         / bf2.pow(&bf96)
 }
-pub async fn get_provider() -> Arc<Provider<Http>> {
-    let provider =
-        Provider::try_from("https://eth-mainnet.g.alchemy.com/v2/I93POQk49QE9O-NuOz7nj7sbiluW76it")
-            .unwrap();
+
+/// Env var holding the HTTP endpoint [`get_provider`] connects to, so no endpoint (and
+/// no API key) has to be committed to the repo.
+const HTTP_ENDPOINT_ENV_VAR: &str = "ARBITER_HTTP_ENDPOINT";
+
+/// Connect to a user-supplied HTTP endpoint.
+pub async fn get_provider_from_endpoint(endpoint: &str) -> Arc<Provider<Http>> {
+    let provider = Provider::try_from(endpoint).unwrap();
+    Arc::new(provider)
+}
+
+/// Connect to a user-supplied WebSocket endpoint. The resulting `Provider<Ws>`
+/// satisfies `M::Provider: PubsubClient`, so a `clairvoyance::uniswap::Pool` or
+/// [`multi_pool::MultiPoolMonitor`] built from it can use `monitor_pool_subscribed`/
+/// `spawn_subscribed` to receive swaps push-style instead of by polling.
+pub async fn get_ws_provider(endpoint: &str) -> Arc<Provider<Ws>> {
+    let provider = Provider::<Ws>::connect(endpoint).await.unwrap();
     Arc::new(provider)
-    //https://eth-mainnet.g.alchemy.com/v2/I93POQk49QE9O-NuOz7nj7sbiluW76it
 }
-pub async fn get_uniswapv3_factory(
-    provider: Arc<Provider<Http>>,
-) -> UniswapV3Factory<Provider<Http>> {
+
+/// Connect to the HTTP endpoint named by the `ARBITER_HTTP_ENDPOINT` env var.
+/// Prefer [`get_provider_from_endpoint`] when the endpoint is already in hand.
+pub async fn get_provider() -> Arc<Provider<Http>> {
+    let endpoint = std::env::var(HTTP_ENDPOINT_ENV_VAR).unwrap_or_else(|_| {
+        panic!("{HTTP_ENDPOINT_ENV_VAR} must be set to an HTTP RPC endpoint")
+    });
+    get_provider_from_endpoint(&endpoint).await
+}
+pub async fn get_uniswapv3_factory<M: Middleware>(provider: Arc<M>) -> UniswapV3Factory<M> {
     let uniswap_v3_factory_address = "0x1F98431c8aD98523631AE4a59f267346ea31F984"
         .parse::<Address>()
         .unwrap();
     UniswapV3Factory::new(uniswap_v3_factory_address, provider.clone())
 }
-pub async fn get_pool_from_uniswap(
+pub async fn get_pool_from_uniswap<M: Middleware>(
     token_0: Address,
     token_1: Address,
-    factory: UniswapV3Factory<Provider<Http>>,
+    factory: UniswapV3Factory<M>,
 ) -> Vec<Address> {
     // BP 10000, 3000, 500, 100
     let pool_500 = factory
@@ -71,27 +92,21 @@ pub async fn get_pool_from_uniswap(
         .unwrap();
     vec![pool_100, pool_500, pool_3000, pool_10000]
 }
-pub async fn get_pool_objects(
+pub async fn get_pool_objects<M: Middleware>(
     addresses: Vec<Address>,
-    provider: Arc<Provider<Http>>,
-) -> Vec<IUniswapV3Pool<Provider<Http>>> {
-    let mut vec: Vec<IUniswapV3Pool<Provider<Http>>> = vec![];
+    provider: Arc<M>,
+) -> Vec<IUniswapV3Pool<M>> {
+    let mut vec: Vec<IUniswapV3Pool<M>> = vec![];
     for address in addresses {
         let uniswap_pool = &mut vec![IUniswapV3Pool::new(address, provider.clone())];
         vec.append(uniswap_pool);
     }
     vec
 }
-// pub async fn multi_thread_listener(pools: Vec<IUniswapV3Pool<Provider<Http>>>) {
-//     for pool in pools {
-//         // tokio::spawn(future)
-//         let thread = thread::spawn(move || {
-//             monitor_pool(&pool);
-//         });
-//     }
-// }
+// Superseded by `multi_pool::MultiPoolMonitor`, which spawns one task per pool and
+// multiplexes normalized updates onto a single channel instead of blocking per-pool.
 
-pub async fn monitor_pool(pool: &IUniswapV3Pool<Provider<Http>>) {
+pub async fn monitor_pool<M: Middleware>(pool: &IUniswapV3Pool<M>) {
     let two: BigFloat = 2.0.into();
     let ten: BigFloat = 10.0.into();
     let swap_events = pool.swap_filter();
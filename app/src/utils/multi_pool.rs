@@ -0,0 +1,259 @@
+//! Concurrent multi-pool swap monitoring, multiplexed onto a single channel.
+//!
+//! Supersedes the old single-task, stdout-only `monitor_pool`: spawns one task per
+//! pool's swap subscription (e.g. all fee tiers for a token pair from
+//! `get_pool_from_uniswap`/`get_pool_objects`) and forwards normalized updates, plus a
+//! cross-pool "best price changed" event, onto a [`broadcast`] channel consumers can
+//! subscribe to.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bindings::i_uniswap_v3_pool::IUniswapV3Pool;
+use ethers::prelude::*;
+use ethers::providers::PubsubClient;
+use ethers::types::{Address, U256};
+use num_bigfloat::BigFloat;
+use tokio::sync::{broadcast, Mutex};
+
+use super::convert;
+
+/// A normalized swap update for one pool, emitted onto a [`MultiPoolMonitor`]'s channel.
+#[derive(Debug, Clone)]
+pub struct SwapUpdate {
+    pub pool: Address,
+    pub tick: i32,
+    pub liquidity: u128,
+    pub sqrt_price_x96: U256,
+    /// `convert(sqrt_price_x96)^2`: comparable across fee tiers of the same pair
+    /// without needing token decimals, but not a decimal-normalized human price —
+    /// rescale with `clairvoyance::uniswap::compute_price` for that.
+    pub relative_price: BigFloat,
+}
+
+/// An event on a [`MultiPoolMonitor`]'s unified channel.
+#[derive(Debug, Clone)]
+pub enum PoolEvent {
+    /// A swap landed in one of the monitored pools.
+    Swap(SwapUpdate),
+    /// The best (highest `relative_price`) pool across all monitored fee tiers changed.
+    BestPriceChanged {
+        pool: Address,
+        relative_price: BigFloat,
+    },
+}
+
+/// Cached per-pool state, updated as swaps arrive so other code can read current prices
+/// without re-querying the chain.
+#[derive(Debug, Clone, Default)]
+struct PoolState {
+    tick: i32,
+    liquidity: u128,
+    sqrt_price_x96: U256,
+}
+
+impl PoolState {
+    fn relative_price(&self) -> BigFloat {
+        convert(self.sqrt_price_x96).pow(&BigFloat::from(2))
+    }
+}
+
+/// Concurrently monitors a set of pools (e.g. all fee tiers for one token pair),
+/// spawning one `tokio::task` per pool's swap subscription and multiplexing normalized
+/// updates onto a single broadcast channel, plus a cross-pool "best price changed" event.
+pub struct MultiPoolMonitor {
+    sender: broadcast::Sender<PoolEvent>,
+    states: Arc<Mutex<HashMap<Address, PoolState>>>,
+}
+
+impl MultiPoolMonitor {
+    /// Spawn one task per pool in `pools`, each polling that pool's swap events via
+    /// `.stream()` and forwarding normalized updates onto the returned monitor's
+    /// channel. Works for any `M`; when `M`'s transport supports pub/sub (e.g.
+    /// `Provider<Ws>`), prefer [`MultiPoolMonitor::spawn_subscribed`] instead, which
+    /// receives swaps push-style.
+    pub fn spawn<M: Middleware + 'static>(pools: Vec<IUniswapV3Pool<M>>) -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        let states = Arc::new(Mutex::new(HashMap::new()));
+        let last_best = Arc::new(Mutex::new(None::<Address>));
+
+        for pool in pools {
+            let sender = sender.clone();
+            let states = states.clone();
+            let last_best = last_best.clone();
+
+            tokio::spawn(async move {
+                let address = pool.address();
+                states.lock().await.insert(address, PoolState::default());
+
+                let swap_events = pool.swap_filter();
+                let mut swap_stream = match swap_events.stream().await {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+
+                while let Some(Ok(event)) = swap_stream.next().await {
+                    let state = PoolState {
+                        tick: event.tick,
+                        liquidity: event.liquidity,
+                        sqrt_price_x96: event.sqrt_price_x96,
+                    };
+                    let relative_price = state.relative_price();
+                    states.lock().await.insert(address, state);
+
+                    let _ = sender.send(PoolEvent::Swap(SwapUpdate {
+                        pool: address,
+                        tick: event.tick,
+                        liquidity: event.liquidity,
+                        sqrt_price_x96: event.sqrt_price_x96,
+                        relative_price,
+                    }));
+
+                    if let Some((best_pool, best_price)) = Self::best_price(&states).await {
+                        let mut last_best = last_best.lock().await;
+                        if *last_best != Some(best_pool) {
+                            *last_best = Some(best_pool);
+                            let _ = sender.send(PoolEvent::BestPriceChanged {
+                                pool: best_pool,
+                                relative_price: best_price,
+                            });
+                        }
+                    }
+                }
+            });
+        }
+
+        Self { sender, states }
+    }
+
+    /// Like [`MultiPoolMonitor::spawn`], but subscribes to each pool's swap events
+    /// (`eth_subscribe`) instead of polling for them, so updates arrive push-style.
+    /// Only available when `M`'s transport supports pub/sub (e.g. `Provider<Ws>`).
+    pub fn spawn_subscribed<M: Middleware + 'static>(pools: Vec<IUniswapV3Pool<M>>) -> Self
+    where
+        M::Provider: PubsubClient,
+    {
+        let (sender, _) = broadcast::channel(1024);
+        let states = Arc::new(Mutex::new(HashMap::new()));
+        let last_best = Arc::new(Mutex::new(None::<Address>));
+
+        for pool in pools {
+            let sender = sender.clone();
+            let states = states.clone();
+            let last_best = last_best.clone();
+
+            tokio::spawn(async move {
+                let address = pool.address();
+                states.lock().await.insert(address, PoolState::default());
+
+                let swap_events = pool.swap_filter();
+                let mut swap_stream = match swap_events.subscribe().await {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+
+                while let Some(Ok(event)) = swap_stream.next().await {
+                    let state = PoolState {
+                        tick: event.tick,
+                        liquidity: event.liquidity,
+                        sqrt_price_x96: event.sqrt_price_x96,
+                    };
+                    let relative_price = state.relative_price();
+                    states.lock().await.insert(address, state);
+
+                    let _ = sender.send(PoolEvent::Swap(SwapUpdate {
+                        pool: address,
+                        tick: event.tick,
+                        liquidity: event.liquidity,
+                        sqrt_price_x96: event.sqrt_price_x96,
+                        relative_price,
+                    }));
+
+                    if let Some((best_pool, best_price)) = Self::best_price(&states).await {
+                        let mut last_best = last_best.lock().await;
+                        if *last_best != Some(best_pool) {
+                            *last_best = Some(best_pool);
+                            let _ = sender.send(PoolEvent::BestPriceChanged {
+                                pool: best_pool,
+                                relative_price: best_price,
+                            });
+                        }
+                    }
+                }
+            });
+        }
+
+        Self { sender, states }
+    }
+
+    /// Subscribe to the unified event channel.
+    pub fn subscribe(&self) -> broadcast::Receiver<PoolEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Read each monitored pool's last-known relative price, keyed by address.
+    pub async fn current_prices(&self) -> HashMap<Address, BigFloat> {
+        self.states
+            .lock()
+            .await
+            .iter()
+            .map(|(address, state)| (*address, state.relative_price()))
+            .collect()
+    }
+
+    async fn best_price(
+        states: &Arc<Mutex<HashMap<Address, PoolState>>>,
+    ) -> Option<(Address, BigFloat)> {
+        states
+            .lock()
+            .await
+            .iter()
+            .map(|(address, state)| (*address, state.relative_price()))
+            .fold(None, |best, (address, price)| match best {
+                Some((_, best_price)) if best_price >= price => best,
+                _ => Some((address, price)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(sqrt_price_x96: u128) -> PoolState {
+        PoolState {
+            tick: 0,
+            liquidity: 100,
+            sqrt_price_x96: U256::from(sqrt_price_x96),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_best_price_picks_highest_relative_price() {
+        let low = Address::from_low_u64_be(1);
+        let high = Address::from_low_u64_be(2);
+        let states = Arc::new(Mutex::new(HashMap::from([
+            (low, state(1u128 << 96)),   // relative_price 1.0
+            (high, state(2u128 << 96)),  // relative_price 4.0
+        ])));
+
+        let (best_pool, best_price) = MultiPoolMonitor::best_price(&states).await.unwrap();
+
+        assert_eq!(best_pool, high);
+        assert_eq!(best_price, state(2u128 << 96).relative_price());
+    }
+
+    #[tokio::test]
+    async fn test_current_prices_reports_each_monitored_pool() {
+        let pool_a = Address::from_low_u64_be(1);
+        let monitor = MultiPoolMonitor {
+            sender: broadcast::channel(8).0,
+            states: Arc::new(Mutex::new(HashMap::from([(pool_a, state(1u128 << 96))]))),
+        };
+
+        let prices = monitor.current_prices().await;
+
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices[&pool_a], BigFloat::from(1));
+    }
+}
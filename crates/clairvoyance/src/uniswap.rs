@@ -2,10 +2,20 @@
 //!
 //! Clairvoyance is the monitoring, modelling and simulation suite of Arbiter.
 
+pub mod amm;
+pub mod rate;
+pub mod stableswap;
+
 use std::sync::Arc;
 
+use async_trait::async_trait;
 use bindings::{i_uniswap_v3_pool::IUniswapV3Pool, uniswap_v3_factory::UniswapV3Factory};
-use ethers::{abi::Address, prelude::*, providers::Provider, types::H160};
+use ethers::{
+    abi::Address,
+    prelude::*,
+    providers::{Provider, PubsubClient},
+    types::H160,
+};
 use num_bigfloat::BigFloat;
 use utils::{
     chain_tools::convert_q64_96,
@@ -16,12 +26,29 @@ use crate::clairerror::ClairvoyanceError::{
     FeeTierDoesNotExist, PoolDoesNotExist, TokenDoesNotExist,
 };
 
+pub use self::amm::{AmmPool, PriceImpact, PriceImpactTarget};
+pub use self::rate::TargetRateSource;
+
+/// Which leg of a [`Pool`] is the liquid-staking derivative a [`TargetRateSource`]
+/// prices, for pools that pair one against the other (e.g. an LST/ETH pair).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatedLeg {
+    Token0,
+    Token1,
+}
+
 /// Uniswap V3 factory address.
 const FACTORY: &str = "0x1F98431c8aD98523631AE4a59f267346ea31F984";
 
-/// Representation of a pool.
-#[derive(Debug, Clone)]
-pub struct Pool {
+/// Representation of a pool, generic over the connection used to reach it.
+///
+/// `M` is bounded by [`Middleware`] so a `Pool` can be driven by an HTTP
+/// `Provider<Http>`, a `Provider<Ws>`, or a `Provider<MockProvider>` in tests.
+/// [`Pool::monitor_pool`] polls for swap events and works for any `M`; when `M`'s
+/// transport supports pub/sub (e.g. `Provider<Ws>`), [`Pool::monitor_pool_subscribed`]
+/// receives them push-style instead.
+#[derive(Clone)]
+pub struct Pool<M: Middleware> {
     /// Token 0.
     token_0: Token,
     /// Token 1.
@@ -30,29 +57,57 @@ pub struct Pool {
     bp: u32,
     /// Address of the pool.
     address: H160,
-    /// Factory that created the pool. This could be generic in future.
-    factory: UniswapV3Factory<Provider<Http>>,
+    /// Factory that created the pool.
+    factory: UniswapV3Factory<M>,
     /// Pool contract object.
-    inner: IUniswapV3Pool<Provider<Http>>,
+    inner: IUniswapV3Pool<M>,
+    /// The pool's on-chain token0, which may differ from `token_0` if the factory
+    /// sorted the pair the other way around.
+    pool_token_0: H160,
     /// Current Tick.
     tick: i32,
     /// Current liquidity.
     liquidity: u128,
     /// sqrt_price_x96
     sqrt_price_x96: ethers::types::U256,
+    /// Which leg, if any, is a liquid-staking derivative priced by `rate_source`.
+    rated_leg: Option<RatedLeg>,
+    /// Cached target rate, refreshed from `rate_source` during `_update_pool`.
+    rate: BigFloat,
+    /// Optional oracle for `rated_leg`'s redemption rate against its underlying.
+    rate_source: Option<Arc<dyn TargetRateSource + Send + Sync>>,
 }
 
+impl<M: Middleware + std::fmt::Debug> std::fmt::Debug for Pool<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pool")
+            .field("token_0", &self.token_0)
+            .field("token_1", &self.token_1)
+            .field("bp", &self.bp)
+            .field("address", &self.address)
+            .field("tick", &self.tick)
+            .field("liquidity", &self.liquidity)
+            .field("sqrt_price_x96", &self.sqrt_price_x96)
+            .field("rated_leg", &self.rated_leg)
+            .field("rate", &self.rate)
+            .finish()
+    }
+}
+
+/// A [`Pool`] reached over plain HTTP, polling for events.
+pub type HttpPool = Pool<Provider<Http>>;
+/// A [`Pool`] reached over a WebSocket, so [`Pool::monitor_pool_subscribed`] (unlike
+/// [`HttpPool`], which can only use the polling [`Pool::monitor_pool`]) can receive
+/// swaps push-style via `eth_subscribe`.
+pub type WsPool = Pool<Provider<Ws>>;
+
 // ----
 //TODO: HANDLE THE CASE WHERE THE POOL DOES NOT EXIST
 // ----
-impl Pool {
-    /// Public constructor function that instantiates a `Pool`.
-    pub async fn new(
-        token_0: Token,
-        token_1: Token,
-        bp: u32,
-        provider: Arc<Provider<Http>>,
-    ) -> Pool {
+impl<M: Middleware> Pool<M> {
+    /// Public constructor function that instantiates a `Pool` over any user-supplied
+    /// middleware (HTTP, WS, or a mocked provider in tests).
+    pub async fn new(token_0: Token, token_1: Token, bp: u32, provider: Arc<M>) -> Pool<M> {
         match bp {
             1 | 5 | 30 | 100 => (),
             _ => panic!("{}", FeeTierDoesNotExist { bp }),
@@ -87,16 +142,44 @@ impl Pool {
             );
         }
 
+        let inner = IUniswapV3Pool::new(pool_address, provider.clone());
+        let pool_token_0 = inner.token_0().call().await.unwrap();
+
         Pool {
             token_0,
             token_1,
             bp,
             address: pool_address,
             factory,
-            inner: IUniswapV3Pool::new(pool_address, provider.clone()),
+            inner,
+            pool_token_0,
             tick: 0,
             liquidity: 0,
             sqrt_price_x96: ethers::types::U256::zero(),
+            rated_leg: None,
+            rate: BigFloat::from(1),
+            rate_source: None,
+        }
+    }
+
+    /// Attach a target-rate oracle for `leg`, a liquid-staking derivative whose
+    /// redemption value drifts against its underlying (e.g. an LST/ETH pair).
+    /// [`Pool::rated_spot_price`] then scales the raw pool price by the rate (dividing
+    /// for a `Token0` leg, multiplying for a `Token1` leg) to give the fair price.
+    /// The rate is refreshed from `source` each time [`Pool::_update_pool`] runs.
+    pub fn with_target_rate(
+        mut self,
+        leg: RatedLeg,
+        source: Arc<dyn TargetRateSource + Send + Sync>,
+    ) -> Self {
+        self.rated_leg = Some(leg);
+        self.rate_source = Some(source);
+        self
+    }
+
+    async fn refresh_target_rate(&mut self) {
+        if let Some(source) = self.rate_source.clone() {
+            self.rate = source.rate().await;
         }
     }
 
@@ -128,15 +211,21 @@ impl Pool {
     }
 
     /// Get the pool factory.
-    pub fn get_factory(&self) -> UniswapV3Factory<Provider<Http>> {
+    pub fn get_factory(&self) -> UniswapV3Factory<M> {
         self.factory.clone()
     }
 
     /// Get the pool contract.
-    pub fn get_contract(&self) -> IUniswapV3Pool<Provider<Http>> {
+    pub fn get_contract(&self) -> IUniswapV3Pool<M> {
         self.inner.clone()
     }
 
+    /// Get the pool's on-chain token0 (may differ from `get_tokens().0` if the factory
+    /// sorted the pair the other way around).
+    pub fn get_pool_token_0(&self) -> H160 {
+        self.pool_token_0
+    }
+
     /// Get the pool sqrt_price.
     /// More information regarding how to derive pricing can be found in the whitepaper.
     pub fn get_sqrt_price_x96(&self) -> ethers::types::U256 {
@@ -155,15 +244,32 @@ impl Pool {
         self.sqrt_price_x96 = sqrt_price_x96;
     }
 
-    /// Updates the pool tick and liquidity manually with a contract call.
+    /// Updates the pool tick and liquidity manually with a contract call, and refreshes
+    /// the target rate if a [`TargetRateSource`] is attached.
     pub async fn _update_pool(&mut self) {
         let slot_0 = self.inner.slot_0().call().await.unwrap();
         self.set_liquidity(self.inner.liquidity().call().await.unwrap());
         self.set_tick(slot_0.1);
-        self.set_sqrt_price_x96(slot_0.0)
+        self.set_sqrt_price_x96(slot_0.0);
+        self.refresh_target_rate().await;
+    }
+
+    /// Like [`compute_price`], but if a [`TargetRateSource`] is attached, scales the
+    /// result so it reflects the rated leg's accruing redemption rate rather than the
+    /// raw pool ratio: a `Token0` rate divides it out, a `Token1` rate multiplies it in.
+    pub fn rated_spot_price(&self) -> BigFloat {
+        let raw = compute_price(self.get_tokens(), self.sqrt_price_x96, self.pool_token_0);
+        match self.rated_leg {
+            Some(RatedLeg::Token0) => raw.div(&self.rate),
+            Some(RatedLeg::Token1) => raw.mul(&self.rate),
+            None => raw,
+        }
     }
 
     /// Monitor a pool for swap events and print to standard output.
+    /// Polls via `eth_newFilter`/`eth_getFilterChanges` (`.stream()`); works for any `M`.
+    /// When `M`'s transport supports pub/sub (e.g. `Provider<Ws>`), prefer
+    /// [`Pool::monitor_pool_subscribed`] instead, which receives swaps push-style.
     /// TODO: Make it print a `Swap` struct that implements fmt in a special way.
     pub async fn monitor_pool(&mut self) {
         let pool_contract = self.get_contract();
@@ -181,7 +287,7 @@ impl Pool {
         println!("Listening for events...");
 
         let swap_events = pool_contract.swap_filter();
-        let pool_token_0 = pool_contract.token_0().call().await.unwrap();
+        let pool_token_0 = self.get_pool_token_0();
         let mut swap_stream = swap_events.stream().await.unwrap();
 
         while let Some(Ok(event)) = swap_stream.next().await {
@@ -209,20 +315,314 @@ impl Pool {
         }
     }
 
+    /// The tick spacing for the pool's fee tier, per Uniswap V3's fee/spacing table.
+    fn tick_spacing(&self) -> i32 {
+        match self.bp {
+            1 => 1,
+            5 => 10,
+            30 => 60,
+            100 => 200,
+            bp => unreachable!("fee tier {bp} rejected in Pool::new"),
+        }
+    }
+
+    /// Find the next initialized tick in the swap direction, searching the pool's tick
+    /// bitmap one word at a time, mirroring Uniswap's `TickBitmap.nextInitializedTickWithinOneWord`.
+    /// Returns the tick boundary and whether it is actually initialized (liquidity data exists
+    /// there) versus simply the edge of the searched word.
+    async fn next_initialized_tick(&self, tick: i32, zero_for_one: bool) -> (i32, bool) {
+        let spacing = self.tick_spacing();
+        let mut compressed = tick / spacing;
+        if tick % spacing != 0 && tick < 0 {
+            compressed -= 1;
+        }
+        if !zero_for_one {
+            compressed += 1;
+        }
+
+        let word_pos = (compressed >> 8) as i16;
+        let bit_pos = (compressed & 0xff) as u8;
+        let bitmap_word: U256 = self.inner.tick_bitmap(word_pos).call().await.unwrap();
+
+        if zero_for_one {
+            let mask = (U256::one() << (bit_pos as usize + 1)) - U256::one();
+            let masked = bitmap_word & mask;
+            if masked.is_zero() {
+                ((compressed - bit_pos as i32) * spacing, false)
+            } else {
+                let msb = most_significant_bit(masked) as i32;
+                ((compressed - (bit_pos as i32 - msb)) * spacing, true)
+            }
+        } else {
+            let mask = !((U256::one() << bit_pos as usize) - U256::one());
+            let masked = bitmap_word & mask;
+            if masked.is_zero() {
+                // `compressed` was already advanced by one above, so don't add another.
+                ((compressed + (255 - bit_pos as i32)) * spacing, false)
+            } else {
+                let lsb = least_significant_bit(masked) as i32;
+                ((compressed + (lsb - bit_pos as i32)) * spacing, true)
+            }
+        }
+    }
+
+    /// Read `liquidityNet` at `tick` and apply it in the direction of travel: crossing a
+    /// tick going down (`zero_for_one`) removes the liquidity that tick added on the way up.
+    async fn liquidity_net_at(&self, tick: i32, zero_for_one: bool, liquidity: u128) -> u128 {
+        let tick_info = self.inner.ticks(tick).call().await.unwrap();
+        let liquidity_net = tick_info.1;
+        let delta = if zero_for_one {
+            -liquidity_net
+        } else {
+            liquidity_net
+        };
+        (liquidity as i128 + delta).max(0) as u128
+    }
+
     /// Calculate the amount you would have to swap in order to have a swap that causes
-    /// a given price impact.
-    pub fn price_impact() {
-        todo!()
+    /// a given price impact, or to realize a given output amount, whichever `target`
+    /// selects. Respects concentrated liquidity: the price may cross one or more
+    /// initialized ticks, each with its own active liquidity, before the target is
+    /// reached. `zero_for_one` is `true` for a token0 -> token1 swap (price moving down).
+    ///
+    /// Callers should `_update_pool().await` first so `tick`/`liquidity`/`sqrt_price_x96`
+    /// reflect current on-chain state. Returns the gross input amount (fee included), the
+    /// realized output amount, and the average realized price.
+    pub async fn price_impact(&self, zero_for_one: bool, target: PriceImpactTarget) -> PriceImpact {
+        let fee_fraction = BigFloat::from(self.bp * 100).div(&BigFloat::from(1_000_000));
+
+        let sqrt_price_target = match &target {
+            PriceImpactTarget::Impact(impact) => {
+                let price_multiplier = if zero_for_one {
+                    BigFloat::from(1).sub(impact)
+                } else {
+                    BigFloat::from(1).add(impact)
+                };
+                Some(convert_q64_96(self.sqrt_price_x96).mul(&price_multiplier.sqrt()))
+            }
+            PriceImpactTarget::AmountOut(_) => None,
+        };
+        let target_amount_out = match &target {
+            PriceImpactTarget::AmountOut(amount) => Some(amount.clone()),
+            PriceImpactTarget::Impact(_) => None,
+        };
+
+        let mut tick = self.tick;
+        let mut liquidity = self.liquidity;
+        let mut sqrt_price = convert_q64_96(self.sqrt_price_x96);
+        let mut amount_in_net = BigFloat::from(0);
+        let mut amount_out = BigFloat::from(0);
+
+        loop {
+            let (next_tick, initialized) = self.next_initialized_tick(tick, zero_for_one).await;
+            let sqrt_price_next = tick_to_sqrt_price(next_tick);
+
+            // Don't walk past a requested target price within this segment.
+            let segment_end = match &sqrt_price_target {
+                Some(target) if zero_for_one && target > &sqrt_price_next => *target,
+                Some(target) if !zero_for_one && target < &sqrt_price_next => *target,
+                _ => sqrt_price_next,
+            };
+
+            if liquidity != 0 {
+                let l = bigfloat_from_liquidity(liquidity);
+                let (segment_in, segment_out) = if zero_for_one {
+                    (
+                        l.mul(&BigFloat::from(1).div(&segment_end).sub(&BigFloat::from(1).div(&sqrt_price))),
+                        l.mul(&sqrt_price.sub(&segment_end)),
+                    )
+                } else {
+                    (
+                        l.mul(&segment_end.sub(&sqrt_price)),
+                        l.mul(&BigFloat::from(1).div(&sqrt_price).sub(&BigFloat::from(1).div(&segment_end))),
+                    )
+                };
+
+                if let Some(target_amount_out) = &target_amount_out {
+                    let remaining = target_amount_out.sub(&amount_out);
+                    if segment_out >= remaining && segment_out > BigFloat::from(0) {
+                        // The target is reached partway through this segment. `segment_out`
+                        // is linear in `sqrtP`, but `segment_in` is linear in `1/sqrtP`, so
+                        // pro-rating `segment_in` by `remaining / segment_out` (the fraction
+                        // on the output side) does not carry over to the input side. Solve
+                        // for the exact `sqrtP` at which `remaining` is reached instead, and
+                        // price the partial range directly off that.
+                        let partial_sqrt_price = if zero_for_one {
+                            sqrt_price.sub(&remaining.div(&l))
+                        } else {
+                            BigFloat::from(1)
+                                .div(&BigFloat::from(1).div(&sqrt_price).sub(&remaining.div(&l)))
+                        };
+                        let partial_in = if zero_for_one {
+                            l.mul(
+                                &BigFloat::from(1)
+                                    .div(&partial_sqrt_price)
+                                    .sub(&BigFloat::from(1).div(&sqrt_price)),
+                            )
+                        } else {
+                            l.mul(&partial_sqrt_price.sub(&sqrt_price))
+                        };
+                        amount_in_net = amount_in_net.add(&partial_in);
+                        amount_out = target_amount_out.clone();
+                        break;
+                    }
+                }
+
+                amount_in_net = amount_in_net.add(&segment_in);
+                amount_out = amount_out.add(&segment_out);
+            }
+
+            sqrt_price = segment_end;
+            // Going down, step just below the crossed boundary so the next search does not
+            // return the same tick again.
+            tick = if zero_for_one { next_tick - 1 } else { next_tick };
+
+            if let Some(target) = &sqrt_price_target {
+                let reached = if zero_for_one {
+                    sqrt_price <= *target
+                } else {
+                    sqrt_price >= *target
+                };
+                if reached {
+                    break;
+                }
+            }
+
+            if initialized {
+                liquidity = self.liquidity_net_at(next_tick, zero_for_one, liquidity).await;
+            } else if next_tick <= -887272 || next_tick >= 887272 {
+                // Past Uniswap V3's MIN_TICK/MAX_TICK: the target is unreachable with the
+                // liquidity currently in the pool. An uninitialized boundary short of that
+                // is only the edge of a bitmap word, so the search continues into the next.
+                break;
+            }
+        }
+
+        let amount_in = amount_in_net.div(&BigFloat::from(1).sub(&fee_fraction));
+        let average_price = if amount_in > BigFloat::from(0) {
+            amount_out.div(&amount_in)
+        } else {
+            BigFloat::from(0)
+        };
+
+        PriceImpact {
+            amount_in,
+            amount_out,
+            average_price,
+        }
+    }
+}
+
+impl<M: Middleware> Pool<M>
+where
+    M::Provider: PubsubClient,
+{
+    /// Like [`Pool::monitor_pool`], but subscribes to swap events (`eth_subscribe`)
+    /// instead of polling for them, so swaps arrive push-style. Only available when
+    /// `M`'s transport supports pub/sub (e.g. `Provider<Ws>`, not `Provider<Http>`),
+    /// which real push delivery requires.
+    pub async fn monitor_pool_subscribed(&mut self) {
+        let pool_contract = self.get_contract();
+        let pool_tokens = self.get_tokens();
+        let pool_bp = self.get_bp();
+
+        println!(
+            "...Got Pool (token0, token1, bps, address): {}, {}, {}, {:#?}\n",
+            pool_tokens.0.name,
+            pool_tokens.1.name,
+            pool_bp,
+            pool_contract.address()
+        );
+
+        println!("Subscribing for events...");
+
+        let swap_events = pool_contract.swap_filter();
+        let pool_token_0 = self.get_pool_token_0();
+        let mut swap_stream = swap_events.subscribe().await.unwrap();
+
+        while let Some(Ok(event)) = swap_stream.next().await {
+            let (tick, liq, sqrtprice) = (event.tick, event.liquidity, event.sqrt_price_x96);
+            self.set_tick(tick);
+            self.set_liquidity(liq);
+            self.set_sqrt_price_x96(sqrtprice);
+            println!("------------NEW SWAP------------");
+            println!("Pool:      {:#?}", pool_contract.address());
+            println!("Sender:    {:#?}", event.sender);
+            println!("Recipient: {:#?}", event.recipient);
+            println!("Amount_0:  {:#?}", event.amount_0); // I256
+            println!("Amount_1:  {:#?}", event.amount_1); // I256
+            println!("Liquidity: {:#?}", event.liquidity); // u128
+            println!("Tick:      {:#?}", event.tick); // i32
+            println!(
+                "Price:     {:#?}",
+                compute_price(pool_tokens.clone(), event.sqrt_price_x96, pool_token_0,).to_string()
+            );
+
+            // Check tick, price, and liquidity where updated
+            assert_eq!(event.tick, self.get_tick());
+            assert_eq!(event.liquidity, self.get_liquidity());
+            assert_eq!(event.sqrt_price_x96, self.get_sqrt_price_x96());
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> AmmPool for Pool<M> {
+    fn spot_price(&self) -> BigFloat {
+        self.rated_spot_price()
+    }
+
+    async fn update(&mut self) {
+        self._update_pool().await
+    }
+
+    async fn monitor(&mut self) {
+        self.monitor_pool().await
+    }
+
+    async fn price_impact(&self, zero_for_one: bool, target: PriceImpactTarget) -> PriceImpact {
+        Pool::price_impact(self, zero_for_one, target).await
     }
 }
 
+/// `1.0001^(tick / 2)`, i.e. the `sqrtPriceX96` (de-scaled to a plain ratio) at `tick`,
+/// per the Uniswap V3 whitepaper.
+fn tick_to_sqrt_price(tick: i32) -> BigFloat {
+    BigFloat::from(1.0001).pow(&BigFloat::from(tick).div(&BigFloat::from(2)))
+}
+
+/// Decomposes a `u128` into its high/low `u64` limbs rather than casting (which would
+/// silently truncate any liquidity value exceeding `u64::MAX`), mirroring how `rate.rs`
+/// decomposes a `U256` into a `BigFloat`.
+fn bigfloat_from_liquidity(liquidity: u128) -> BigFloat {
+    let hi = (liquidity >> 64) as u64;
+    let lo = liquidity as u64;
+    BigFloat::from(hi).mul(&BigFloat::from(2).pow(&BigFloat::from(64))).add(&BigFloat::from(lo))
+}
+
+/// Index of the highest set bit in `x` (`x` must be nonzero).
+fn most_significant_bit(x: U256) -> u8 {
+    (x.bits() - 1) as u8
+}
+
+/// Index of the lowest set bit in `x` (`x` must be nonzero).
+fn least_significant_bit(x: U256) -> u8 {
+    let mut word = x;
+    let mut i = 0u8;
+    while word.low_u64() & 1 == 0 {
+        word >>= 1;
+        i += 1;
+    }
+    i
+}
+
 /// Wrapper function to easily create a pool.
-pub async fn get_pool(
+pub async fn get_pool<M: Middleware>(
     token0: &String,
     token1: &String,
     bp: &str,
-    provider: Arc<Provider<Http>>,
-) -> Pool {
+    provider: Arc<M>,
+) -> Pool<M> {
     let tokens = get_tokens();
 
     let token_name = token0.clone();
@@ -243,7 +643,7 @@ pub async fn get_pool(
 }
 
 /// Get a sample test pool.
-pub async fn _get_test_pool(bp: String, provider: Arc<Provider<Http>>) -> Pool {
+pub async fn _get_test_pool<M: Middleware>(bp: String, provider: Arc<M>) -> Pool<M> {
     let tokens = get_tokens();
     Pool::new(
         tokens.get("ETH").unwrap().to_owned(),
@@ -276,17 +676,27 @@ mod tests {
     use std::sync::Arc;
 
     use ethers::{abi::Address, providers::*};
-    use utils::{chain_tools, tokens};
+    use num_bigfloat::BigFloat;
+    use utils::tokens;
 
-    use super::Pool;
+    use super::{Pool, PriceImpactTarget, RatedLeg};
 
+    /// Pushes `$address` as the mocked response for the `factory.get_pool` call and
+    /// `$tokens.0`'s address as the mocked response for the `token_0()` call that
+    /// `Pool::new` makes, then asserts the constructed pool resolved to it. This lets
+    /// pool construction be asserted offline, without a live node or an API key.
     macro_rules! create_pool {
         (
             $provider:expr,
+            $mock:expr,
             $tokens:expr,
             $bp:expr,
             $address:expr
         ) => {
+            $mock
+                .push($address.parse::<Address>().unwrap())
+                .unwrap();
+            $mock.push($tokens.0.address).unwrap();
             let pool =
                 Pool::new($tokens.0.clone(), $tokens.1.clone(), $bp, $provider.clone()).await;
 
@@ -296,7 +706,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_pool_from_uniswap() {
-        let provider: Arc<Provider<Http>> = chain_tools::get_provider().await;
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
 
         let tokens = (
             tokens::get_tokens().get("ETH").unwrap().to_owned(),
@@ -305,24 +716,28 @@ mod tests {
 
         create_pool!(
             provider,
+            mock,
             tokens,
             1,
             "0xe0554a476a092703abdb3ef35c80e0d76d32939f"
         );
         create_pool!(
             provider,
+            mock,
             tokens,
             5,
             "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640"
         );
         create_pool!(
             provider,
+            mock,
             tokens,
             30,
             "0x8ad599c3a0ff1de082011efddc58f1908eb6e6d8"
         );
         create_pool!(
             provider,
+            mock,
             tokens,
             100,
             "0x7bea39867e4169dbe237d55c8242a8f2fcdcc387"
@@ -332,19 +747,173 @@ mod tests {
     #[tokio::test]
     #[should_panic]
     async fn test_get_pool_from_uniswap_700() {
-        let provider: Arc<Provider<Http>> = chain_tools::get_provider().await;
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
 
         let tokens = (
             tokens::get_tokens().get("ETH").unwrap().to_owned(),
             tokens::get_tokens().get("USDC").unwrap().to_owned(),
         );
 
-        // This address is arbitrary as pool creation should anyways fail.
+        // This address is arbitrary as pool creation should anyways fail before any
+        // mocked response is consumed: bp 700 is rejected up front.
         create_pool!(
             provider,
+            mock,
             tokens,
             700,
             "0x7bea39867e4169dbe237d55c8242a8f2fcdcc387"
         );
     }
+
+    #[tokio::test]
+    async fn test_update_pool_from_mocked_slot_0() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+
+        let tokens = (
+            tokens::get_tokens().get("ETH").unwrap().to_owned(),
+            tokens::get_tokens().get("USDC").unwrap().to_owned(),
+        );
+
+        let pool_address = "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640";
+        mock.push(pool_address.parse::<Address>().unwrap()).unwrap();
+        mock.push(tokens.0.address).unwrap();
+        let mut pool = Pool::new(tokens.0.clone(), tokens.1.clone(), 5, provider.clone()).await;
+
+        // slot_0() -> (sqrtPriceX96, tick, observationIndex, observationCardinality,
+        // observationCardinalityNext, feeProtocol, unlocked)
+        let sqrt_price_x96 = ethers::types::U256::from(1u128 << 96);
+        mock.push((sqrt_price_x96, 42i32, 0u16, 1u16, 1u16, 0u8, true))
+            .unwrap();
+        mock.push(1_000_000_000u128).unwrap();
+
+        pool._update_pool().await;
+
+        assert_eq!(pool.get_sqrt_price_x96(), sqrt_price_x96);
+        assert_eq!(pool.get_tick(), 42);
+        assert_eq!(pool.get_liquidity(), 1_000_000_000u128);
+    }
+
+    /// Regression test for an off-by-one in the `!zero_for_one`/`masked.is_zero()` arm:
+    /// `compressed` is already advanced by one earlier in the function for this
+    /// direction, so the "ran off the end of the word" case must not add another `+ 1`
+    /// on top of that.
+    #[tokio::test]
+    async fn test_next_initialized_tick_one_for_zero_empty_word() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+
+        let tokens = (
+            tokens::get_tokens().get("ETH").unwrap().to_owned(),
+            tokens::get_tokens().get("USDC").unwrap().to_owned(),
+        );
+
+        let pool_address = "0x8ad599c3a0ff1de082011efddc58f1908eb6e6d8";
+        mock.push(pool_address.parse::<Address>().unwrap()).unwrap();
+        mock.push(tokens.0.address).unwrap();
+        let pool = Pool::new(tokens.0.clone(), tokens.1.clone(), 30, provider.clone()).await;
+
+        // Empty word: no initialized tick anywhere past `bit_pos`.
+        mock.push(ethers::types::U256::zero()).unwrap();
+
+        // tick 0, spacing 60 (bp 30) -> compressed starts at 0, and is advanced to 1 for
+        // the `!zero_for_one` direction before the bitmap is even queried, so bit_pos is 1.
+        let (next_tick, initialized) = pool.next_initialized_tick(0, false).await;
+
+        assert!(!initialized);
+        assert_eq!(next_tick, (1 + (255 - 1)) * 60);
+    }
+
+    /// `price_impact` should carry liquidity across a crossed initialized tick (reading
+    /// `tick_bitmap`/`ticks` as needed) rather than only ever pricing within the segment
+    /// the pool started in.
+    #[tokio::test]
+    async fn test_price_impact_crosses_initialized_tick() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+
+        let tokens = (
+            tokens::get_tokens().get("ETH").unwrap().to_owned(),
+            tokens::get_tokens().get("USDC").unwrap().to_owned(),
+        );
+
+        let pool_address = "0x8ad599c3a0ff1de082011efddc58f1908eb6e6d8";
+        mock.push(pool_address.parse::<Address>().unwrap()).unwrap();
+        mock.push(tokens.0.address).unwrap();
+        let mut pool = Pool::new(tokens.0.clone(), tokens.1.clone(), 30, provider.clone()).await;
+
+        // Seed pool state directly rather than mocking a `_update_pool` round trip: tick
+        // 0, price 1:1, liquidity 500.
+        pool.set_tick(0);
+        pool.set_liquidity(500);
+        pool.set_sqrt_price_x96(ethers::types::U256::from(1u128 << 96));
+
+        // One-for-zero (price increasing): the first word has only tick 60 (bit 1)
+        // initialized, so the walk crosses it, picks up the liquidity that tick adds,
+        // then searches the same word again past bit 1 and finds nothing left.
+        let mut bitmap = ethers::types::U256::zero();
+        bitmap |= ethers::types::U256::one() << 1u32;
+        mock.push(bitmap).unwrap();
+        // ticks(60) -> (liquidityGross, liquidityNet, feeGrowthOutside0X128,
+        // feeGrowthOutside1X128, tickCumulativeOutside, secondsPerLiquidityOutsideX128,
+        // secondsOutside, initialized)
+        mock.push((
+            300u128,
+            300i128,
+            ethers::types::U256::zero(),
+            ethers::types::U256::zero(),
+            0i64,
+            0u64,
+            0u32,
+            true,
+        ))
+        .unwrap();
+        mock.push(bitmap).unwrap();
+
+        // Small enough that it can't be satisfied within the first segment's liquidity
+        // (500) alone, but well within reach once the second segment's liquidity (800)
+        // kicks in: this only resolves correctly if the crossing above actually happened.
+        let target_amount_out = BigFloat::from(10);
+        let impact = pool
+            .price_impact(false, PriceImpactTarget::AmountOut(target_amount_out.clone()))
+            .await;
+
+        assert_eq!(impact.amount_out, target_amount_out);
+        // Hand-computed: segment 1 (tick 0->60, L 500) contributes ~1.4977 of the
+        // target output and ~1.5022 input; the remaining ~8.5023 output is reached
+        // partway into segment 2 (L 800) at an exact `sqrtP`, needing ~8.6456 more
+        // input. Gross input after the 0.3% fee is ~10.1784 — nowhere near the ~19.89
+        // a linear pro-rated-by-output-fraction bug would produce.
+        let expected_amount_in = BigFloat::from(10.1784);
+        assert!(impact.amount_in.sub(&expected_amount_in).abs() < BigFloat::from(0.01));
+    }
+
+    /// A `Token0` target rate should de-scale the raw pool price by dividing it out, and
+    /// a `Token1` rate by multiplying it in, per `rated_spot_price`'s doc comment.
+    #[tokio::test]
+    async fn test_rated_spot_price_scales_by_target_rate() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+
+        let tokens = (
+            tokens::get_tokens().get("ETH").unwrap().to_owned(),
+            tokens::get_tokens().get("USDC").unwrap().to_owned(),
+        );
+
+        let pool_address = "0x8ad599c3a0ff1de082011efddc58f1908eb6e6d8";
+        mock.push(pool_address.parse::<Address>().unwrap()).unwrap();
+        mock.push(tokens.0.address).unwrap();
+        let mut pool = Pool::new(tokens.0.clone(), tokens.1.clone(), 30, provider.clone()).await;
+        pool.set_sqrt_price_x96(ethers::types::U256::from(1u128 << 96));
+
+        let raw = pool.rated_spot_price();
+
+        pool.rated_leg = Some(RatedLeg::Token0);
+        pool.rate = BigFloat::from(2);
+        assert_eq!(pool.rated_spot_price(), raw.div(&BigFloat::from(2)));
+
+        pool.rated_leg = Some(RatedLeg::Token1);
+        assert_eq!(pool.rated_spot_price(), raw.mul(&BigFloat::from(2)));
+    }
 }
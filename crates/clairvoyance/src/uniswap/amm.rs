@@ -0,0 +1,49 @@
+//! DEX-agnostic pool abstraction, so the simulation suite can model more than one AMM
+//! design (Uniswap V3 concentrated liquidity, Curve-style StableSwap, ...) behind a
+//! common interface.
+
+use async_trait::async_trait;
+use num_bigfloat::BigFloat;
+
+/// What an [`AmmPool::price_impact`] call is solving for.
+#[derive(Debug, Clone)]
+pub enum PriceImpactTarget {
+    /// A price impact as a fraction of the current price (e.g. `0.01` for 1%).
+    /// [`super::Pool`] moves the pool price by this fraction;
+    /// [`super::stableswap::StableSwapPool`] targets a realized average price this
+    /// fraction below its current marginal price.
+    Impact(BigFloat),
+    /// Realize (at least) this much output amount.
+    AmountOut(BigFloat),
+}
+
+/// The gross input amount and realized output/average price for an [`AmmPool::price_impact`] call.
+#[derive(Debug, Clone)]
+pub struct PriceImpact {
+    /// Gross input amount, including the pool fee.
+    pub amount_in: BigFloat,
+    /// Output amount realized by the swap.
+    pub amount_out: BigFloat,
+    /// Average realized price (`amount_out / amount_in`, net of fee).
+    pub average_price: BigFloat,
+}
+
+/// A pool that can report a spot price, refresh its cached on-chain state, stream swap
+/// events, and quote the input required for a given price impact, independent of which
+/// DEX it models. Implemented by [`super::Pool`] (Uniswap V3) and
+/// [`super::stableswap::StableSwapPool`] (Curve-style StableSwap).
+#[async_trait]
+pub trait AmmPool {
+    /// Current human-readable price of the pool.
+    fn spot_price(&self) -> BigFloat;
+
+    /// Refresh cached state (ticks/liquidity/reserves, as applicable) from its source.
+    async fn update(&mut self);
+
+    /// Stream swap/update events, keeping cached state in sync as they arrive.
+    async fn monitor(&mut self);
+
+    /// Required input amount for a target price impact or output amount. `zero_for_one`
+    /// selects the swap direction (token0 -> token1 when `true`).
+    async fn price_impact(&self, zero_for_one: bool, target: PriceImpactTarget) -> PriceImpact;
+}